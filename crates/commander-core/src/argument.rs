@@ -0,0 +1,30 @@
+/// How many values an `Argument` consumes, and whether it's required.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgumentType {
+    RequiredSingle,
+    OptionalSingle,
+    RequiredMultiple,
+    OptionalMultiple,
+}
+
+/// A single positional or option argument, as resolved from a `#[command]`
+/// or `#[option]` attribute.
+#[derive(Debug, Clone)]
+pub struct Argument {
+    pub ty: ArgumentType,
+
+    /// Name of an environment variable that supplies this argument's value
+    /// when the user doesn't pass it on the command line.
+    pub env: Option<String>,
+
+    /// Separator used to split `env`'s value into multiple values for
+    /// `RequiredMultiple`/`OptionalMultiple` arguments. Falls back to
+    /// whitespace when unset.
+    pub env_separator: Option<String>,
+
+    /// Declared default value(s), used when the user didn't pass anything
+    /// on the command line and `env` supplied no value: it is unset, absent
+    /// from the process environment, not valid unicode, or (for multi-value
+    /// arguments) splits into no non-empty parts.
+    pub default: Option<Vec<String>>,
+}
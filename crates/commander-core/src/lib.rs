@@ -0,0 +1,5 @@
+mod argument;
+mod raw;
+
+pub use argument::{ Argument, ArgumentType };
+pub use raw::*;
@@ -1,5 +1,6 @@
 use std::iter::FromIterator;
-use crate::{ Instance, ArgumentType, Argument };
+use std::fmt;
+use crate::{ Instance, ArgumentType, Argument, Cli };
 use std::ops::{ Deref, DerefMut };
 
 /// Encapsulation of `Vec<String>`.
@@ -27,31 +28,157 @@ use std::ops::{ Deref, DerefMut };
 /// Any type that implements `From<Raw>` can be used as a parameter type of command processing methods.
 ///
 #[derive(Debug, Clone)]
-pub struct Raw(Vec<String>);
+pub struct Raw {
+    values: Vec<String>,
+    sources: Vec<RawSource>,
+}
+
+/// Where a single value inside a `Raw` came from.
+/// ```ignore
+/// if cli.get::<Raw>("output").is_from_user() {
+///     // the user actually typed --output, not a default or $OUTPUT
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawSource {
+    /// The value was typed on the command line.
+    CommandLine,
+    /// The value came from a declared default.
+    DefaultValue,
+    /// The value was read from an environment variable.
+    Environment,
+}
+
+/// Error produced when a `Raw` value cannot be converted into the type a
+/// command handler asked for.
+///
+/// Carries the offending string, the name of the type conversion was
+/// attempted against, and the index of the value inside the `Raw` (always
+/// `0` for single-value arguments, the element position for multi-value
+/// ones), so a handler can report exactly what went wrong and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawConvertError {
+    value: String,
+    target: String,
+    index: usize,
+}
+
+impl RawConvertError {
+    #[doc(hidden)]
+    pub fn new<V: Into<String>, T: Into<String>>(value: V, target: T, index: usize) -> RawConvertError {
+        RawConvertError {
+            value: value.into(),
+            target: target.into(),
+            index,
+        }
+    }
+
+    /// The string that failed to convert.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// The name of the type (or constraint) the value was converted against.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Position of the offending value within its `Raw`. Always `0` for
+    /// single-value arguments.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for RawConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid value '{}' for {} at index {}", self.value, self.target, self.index)
+    }
+}
+
+impl std::error::Error for RawConvertError {}
+
+/// A fallible counterpart to `From<Raw>`.
+///
+/// `std`'s blanket `impl<T, U: Into<T>> TryFrom<U> for T` means `Raw`'s kept
+/// `From<Raw>` impls already give every `$ty` a (infallible) `TryFrom<Raw>`,
+/// so this crate can't add its own without conflicting. `TryFromRaw` is the
+/// named conversion instead: implement it to get a real error out, and the
+/// matching `From<Raw>` impl below delegates to it and falls back to
+/// `Default` on `Err`.
+pub trait TryFromRaw: Sized {
+    fn try_from_raw(raw: Raw) -> Result<Self, RawConvertError>;
+}
 
 impl Raw {
     #[doc(hidden)]
     #[inline]
     pub fn push(&mut self, ele: String) {
-        (self.0).push(ele);
+        self.push_with_source(ele, RawSource::CommandLine);
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn push_with_source(&mut self, ele: String, source: RawSource) {
+        (self.values).push(ele);
+        (self.sources).push(source);
     }
 
     #[doc(hidden)]
     #[inline]
     pub fn remove(&mut self, idx: usize) -> String {
-        (self.0).remove(idx)
+        self.sources.remove(idx);
+        (self.values).remove(idx)
     }
 
     #[doc(hidden)]
     #[inline]
     pub fn new(v: Vec<String>) -> Raw {
-        Raw(v)
+        let sources = vec![RawSource::CommandLine; v.len()];
+
+        Raw { values: v, sources }
     }
 
     #[doc(hidden)]
     #[inline]
     pub fn is_empty(&self) -> bool {
-        (self.0).len() == 0
+        (self.values).len() == 0
+    }
+
+    /// Where the value at `idx` came from. Defaults to [`RawSource::DefaultValue`]
+    /// for an index with no tracked value, e.g. an empty `Raw`.
+    pub fn value_source(&self, idx: usize) -> RawSource {
+        self.sources.get(idx).cloned().unwrap_or(RawSource::DefaultValue)
+    }
+
+    /// `true` if any of this `Raw`'s values was actually typed on the command
+    /// line, as opposed to coming from a default or an environment variable.
+    pub fn is_from_user(&self) -> bool {
+        self.sources.iter().any(|source| *source == RawSource::CommandLine)
+    }
+
+    /// Converts this `Raw` into `T`, surfacing a [`RawConvertError`] instead
+    /// of silently falling back to a default when the underlying string
+    /// doesn't parse.
+    pub fn try_into_typed<T: TryFromRaw>(self) -> Result<T, RawConvertError> {
+        T::try_from_raw(self)
+    }
+
+    /// Runs every value through a [`RawParser`], collecting them in order.
+    /// Bails out with the first [`RawConvertError`] encountered.
+    pub fn parse_with<P: RawParser>(&self, parser: P) -> Result<Vec<P::Output>, RawConvertError> {
+        self.iter()
+            .enumerate()
+            .map(|(idx, s)| parser.parse_one(s, idx))
+            .collect()
+    }
+
+    /// Like [`Raw::parse_with`], but for single-value arguments: runs the
+    /// parser against the first value (an empty string if none was supplied).
+    pub fn parse_one_with<P: RawParser>(&self, parser: P) -> Result<P::Output, RawConvertError> {
+        let value = self.get(0).map(String::as_str).unwrap_or("");
+
+        parser.parse_one(value, 0)
     }
 
     #[doc(hidden)]
@@ -112,7 +239,7 @@ impl Raw {
         if let Some(arg) = arg {
             let mut iter = ins.args.iter();
 
-            match arg.ty {
+            let mut raw = match arg.ty {
                 ArgumentType::RequiredSingle => {
                     let mut raw = Raw::new(vec![]);
 
@@ -156,11 +283,73 @@ impl Raw {
 
                     raw
                 }
-            }
+            };
+
+            Raw::fill_from_env(&mut raw, arg);
+            Raw::fill_from_default(&mut raw, arg);
+
+            raw
         } else {
             Raw::new(vec![])
         }
     }
+
+    /// When the user didn't supply anything on the command line and nothing
+    /// came from `arg.env` either, fills `raw` from `arg.default`, tagging
+    /// every value as [`RawSource::DefaultValue`].
+    fn fill_from_default(raw: &mut Raw, arg: &Argument) {
+        if !raw.is_empty() {
+            return;
+        }
+
+        let values = match &arg.default {
+            Some(values) => values,
+            None => return,
+        };
+
+        for value in values {
+            raw.push_with_source(value.clone(), RawSource::DefaultValue);
+        }
+    }
+
+    /// When the user didn't supply anything on the command line and `arg`
+    /// declares a fallback environment variable, reads it and fills `raw`
+    /// from it, tagging every value pulled this way as [`RawSource::Environment`].
+    /// Multi-value arguments are split on `arg.env_separator`, falling back
+    /// to whitespace when unset.
+    fn fill_from_env(raw: &mut Raw, arg: &Argument) {
+        if !raw.is_empty() {
+            return;
+        }
+
+        let name = match &arg.env {
+            Some(name) => name,
+            None => return,
+        };
+
+        let value = match std::env::var(name) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+
+        match arg.ty {
+            ArgumentType::RequiredMultiple | ArgumentType::OptionalMultiple => {
+                let parts: Vec<&str> = match &arg.env_separator {
+                    Some(sep) => value.split(sep.as_str()).collect(),
+                    None => value.split_whitespace().collect(),
+                };
+
+                for part in parts {
+                    if !part.is_empty() {
+                        raw.push_with_source(part.to_string(), RawSource::Environment);
+                    }
+                }
+            },
+            ArgumentType::RequiredSingle | ArgumentType::OptionalSingle => {
+                raw.push_with_source(value, RawSource::Environment);
+            }
+        }
+    }
 }
 
 impl FromIterator<String> for Raw {
@@ -179,25 +368,31 @@ impl Deref for Raw {
     type Target = Vec<String>;
 
     fn deref(&self) -> &Vec<String> {
-        &(self.0)
+        &(self.values)
     }
 }
 
 impl DerefMut for Raw {
     fn deref_mut(&mut self) -> &mut Vec<String> {
-        &mut (self.0)
+        &mut (self.values)
     }
 }
 
 macro_rules! impl_primitive {
     ($($ty: ty),*) => {
         $(
+            impl TryFromRaw for $ty {
+                fn try_from_raw(raw: Raw) -> Result<$ty, RawConvertError> {
+                    match raw.get(0) {
+                        None => Ok(<$ty>::default()),
+                        Some(value) => value.parse().map_err(|_| RawConvertError::new(value.clone(), stringify!($ty), 0)),
+                    }
+                }
+            }
+
             impl From<Raw> for $ty {
                 fn from(raw: Raw) -> $ty {
-                    raw.get(0)
-                        .unwrap_or(&String::from("0"))
-                        .parse()
-                        .unwrap_or(<$ty>::default())
+                    <$ty>::try_from_raw(raw).unwrap_or_default()
                 }
             }
         )*
@@ -207,15 +402,21 @@ macro_rules! impl_primitive {
 macro_rules! impl_option {
     ($($ty: ty),*) => {
         $(
-            impl From<Raw> for Option<$ty> {
-                fn from(raw: Raw) -> Option<$ty> {
+            impl TryFromRaw for Option<$ty> {
+                fn try_from_raw(raw: Raw) -> Result<Option<$ty>, RawConvertError> {
                     if raw.is_empty() {
-                        None
+                        Ok(None)
                     } else {
-                        Some(<$ty>::from(raw))
+                        Ok(Some(<$ty>::try_from_raw(raw)?))
                     }
                 }
             }
+
+            impl From<Raw> for Option<$ty> {
+                fn from(raw: Raw) -> Option<$ty> {
+                    Option::<$ty>::try_from_raw(raw).unwrap_or_default()
+                }
+            }
         )*
     };
 }
@@ -223,9 +424,18 @@ macro_rules! impl_option {
 macro_rules! impl_vec {
     ($($ty: ty),*) => {
         $(
+            impl TryFromRaw for Vec<$ty> {
+                fn try_from_raw(raw: Raw) -> Result<Vec<$ty>, RawConvertError> {
+                    raw.iter()
+                        .enumerate()
+                        .map(|(idx, s)| s.parse().map_err(|_| RawConvertError::new(s.clone(), stringify!($ty), idx)))
+                        .collect()
+                }
+            }
+
             impl From<Raw> for Vec<$ty> {
                 fn from(raw: Raw) -> Vec<$ty> {
-                    raw.iter().map(|i| i.parse().unwrap_or(<$ty>::default())).collect()
+                    <Vec<$ty>>::try_from_raw(raw).unwrap_or_default()
                 }
             }
         )*
@@ -235,15 +445,21 @@ macro_rules! impl_vec {
 macro_rules! impl_option_vec {
     ($($ty: ty),*) => {
         $(
-            impl From<Raw> for Option<Vec<$ty>> {
-                fn from(raw: Raw) -> Option<Vec<$ty>> {
+            impl TryFromRaw for Option<Vec<$ty>> {
+                fn try_from_raw(raw: Raw) -> Result<Option<Vec<$ty>>, RawConvertError> {
                     if raw.is_empty() {
-                        None
+                        Ok(None)
                     } else {
-                        Some(<Vec<$ty>>::from(raw))
+                        Ok(Some(<Vec<$ty>>::try_from_raw(raw)?))
                     }
                 }
             }
+
+            impl From<Raw> for Option<Vec<$ty>> {
+                fn from(raw: Raw) -> Option<Vec<$ty>> {
+                    Option::<Vec<$ty>>::try_from_raw(raw).unwrap_or_default()
+                }
+            }
         )*
     };
 }
@@ -262,34 +478,334 @@ impl_all![u8, u16, u32, u64, u128, usize];
 impl_all![f32, f64, bool, char];
 
 
+impl TryFromRaw for String {
+    fn try_from_raw(raw: Raw) -> Result<String, RawConvertError> {
+        Ok(raw.get(0).cloned().unwrap_or_default())
+    }
+}
+
 impl From<Raw> for String {
     fn from(raw: Raw) -> String {
-        raw.get(0).unwrap_or(&String::new()).clone()
+        String::try_from_raw(raw).unwrap_or_default()
+    }
+}
+
+impl TryFromRaw for Vec<String> {
+    fn try_from_raw(raw: Raw) -> Result<Vec<String>, RawConvertError> {
+        Ok(raw.iter().cloned().collect())
     }
 }
 
 impl From<Raw> for Vec<String> {
     fn from(raw: Raw) -> Vec<String> {
-        raw.iter().map(|s| s.clone()).collect()
+        <Vec<String>>::try_from_raw(raw).unwrap_or_default()
+    }
+}
+
+impl TryFromRaw for Option<String> {
+    fn try_from_raw(raw: Raw) -> Result<Option<String>, RawConvertError> {
+        if raw.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(String::try_from_raw(raw)?))
+        }
     }
 }
 
 impl From<Raw> for Option<String> {
     fn from(raw: Raw) -> Option<String> {
+        Option::<String>::try_from_raw(raw).unwrap_or_default()
+    }
+}
+
+impl TryFromRaw for Option<Vec<String>> {
+    fn try_from_raw(raw: Raw) -> Result<Option<Vec<String>>, RawConvertError> {
         if raw.is_empty() {
-            None
+            Ok(None)
         } else {
-            Some(String::from(raw))
+            Ok(Some(<Vec<String>>::try_from_raw(raw)?))
         }
     }
 }
 
 impl From<Raw> for Option<Vec<String>> {
     fn from(raw: Raw) -> Option<Vec<String>> {
-        if raw.is_empty() {
-            None
+        Option::<Vec<String>>::try_from_raw(raw).unwrap_or_default()
+    }
+}
+
+impl Cli {
+    /// Like [`Cli::get`], but returns a [`RawConvertError`] instead of a
+    /// default when `name`'s value doesn't parse as `T`.
+    pub fn get_checked<T: TryFromRaw>(&self, name: &str) -> Result<T, RawConvertError> {
+        let raw: Raw = self.get(name);
+
+        raw.try_into_typed()
+    }
+}
+
+/// A pluggable parser for a single `Raw` value, for arguments that want more
+/// than `FromStr` can give them without a newtype and a hand-written
+/// `From<Raw>`. `idx` is the position of `s` within its `Raw`, for error
+/// reporting on multi-value arguments.
+pub trait RawParser {
+    type Output;
+
+    fn parse_one(&self, s: &str, idx: usize) -> Result<Self::Output, RawConvertError>;
+}
+
+impl<F, O> RawParser for F
+where
+    F: Fn(&str) -> Result<O, RawConvertError>,
+{
+    type Output = O;
+
+    fn parse_one(&self, s: &str, idx: usize) -> Result<O, RawConvertError> {
+        self(s).map_err(|err| RawConvertError { index: idx, ..err })
+    }
+}
+
+/// Parses a value as an `i64` and rejects it if it falls outside
+/// `min..=max`, the way clap's `value_parser!(i64).range(..)` does.
+pub struct RangeParser {
+    min: i64,
+    max: i64,
+}
+
+impl RangeParser {
+    pub fn new(min: i64, max: i64) -> RangeParser {
+        RangeParser { min, max }
+    }
+}
+
+impl RawParser for RangeParser {
+    type Output = i64;
+
+    fn parse_one(&self, s: &str, idx: usize) -> Result<i64, RawConvertError> {
+        let value: i64 = s.parse().map_err(|_| RawConvertError::new(s, "integer", idx))?;
+
+        if value < self.min || value > self.max {
+            Err(RawConvertError::new(s, format!("integer in range {}..={}", self.min, self.max), idx))
+        } else {
+            Ok(value)
+        }
+    }
+}
+
+/// Validates a value against a fixed set of allowed strings, the way clap's
+/// possible-values / enum-like parsers do.
+pub struct PossibleValuesParser {
+    values: Vec<String>,
+}
+
+impl PossibleValuesParser {
+    pub fn new<I, S>(values: I) -> PossibleValuesParser
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        PossibleValuesParser {
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl RawParser for PossibleValuesParser {
+    type Output = String;
+
+    fn parse_one(&self, s: &str, idx: usize) -> Result<String, RawConvertError> {
+        if self.values.iter().any(|value| value == s) {
+            Ok(s.to_string())
         } else {
-            Some(<Vec<String>>::from(raw))
+            Err(RawConvertError::new(s, format!("one of [{}]", self.values.join(", ")), idx))
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_from_raw_parses_present_value() {
+        let raw = Raw::new(vec!["42".to_string()]);
+
+        assert_eq!(i32::try_from_raw(raw), Ok(42));
+    }
+
+    #[test]
+    fn try_from_raw_reports_parse_failure() {
+        let raw = Raw::new(vec!["abc".to_string()]);
+        let err = i32::try_from_raw(raw).unwrap_err();
+
+        assert_eq!(err.value(), "abc");
+        assert_eq!(err.target(), "i32");
+        assert_eq!(err.index(), 0);
+    }
+
+    #[test]
+    fn try_from_raw_defaults_a_missing_value_without_erroring() {
+        assert_eq!(i32::try_from_raw(Raw::new(vec![])), Ok(0));
+        assert_eq!(bool::try_from_raw(Raw::new(vec![])), Ok(false));
+        assert_eq!(char::try_from_raw(Raw::new(vec![])), Ok(char::default()));
+    }
+
+    #[test]
+    fn try_from_raw_vec_reports_index_of_bad_element() {
+        let raw = Raw::new(vec!["1".to_string(), "x".to_string(), "3".to_string()]);
+        let err = <Vec<i32>>::try_from_raw(raw).unwrap_err();
+
+        assert_eq!(err.index(), 1);
+        assert_eq!(err.value(), "x");
+    }
+
+    #[test]
+    fn from_raw_falls_back_to_default_on_parse_failure() {
+        let raw = Raw::new(vec!["abc".to_string()]);
+
+        assert_eq!(i32::from(raw), 0);
+    }
+
+    #[test]
+    fn push_tags_command_line_source() {
+        let mut raw = Raw::new(vec![]);
+        raw.push("a".to_string());
+
+        assert_eq!(raw.value_source(0), RawSource::CommandLine);
+        assert!(raw.is_from_user());
+    }
+
+    #[test]
+    fn empty_raw_has_no_user_supplied_value() {
+        let raw = Raw::new(vec![]);
+
+        assert!(!raw.is_from_user());
+        assert_eq!(raw.value_source(0), RawSource::DefaultValue);
+    }
+
+    #[test]
+    fn fill_from_default_tags_default_value_source() {
+        let mut raw = Raw::new(vec![]);
+        let arg = Argument {
+            ty: ArgumentType::OptionalSingle,
+            env: None,
+            env_separator: None,
+            default: Some(vec!["fallback".to_string()]),
+        };
+
+        Raw::fill_from_default(&mut raw, &arg);
+
+        assert_eq!(raw.value_source(0), RawSource::DefaultValue);
+        assert!(!raw.is_from_user());
+    }
+
+    #[test]
+    fn fill_from_default_does_not_override_a_user_supplied_value() {
+        let mut raw = Raw::new(vec!["typed".to_string()]);
+        let arg = Argument {
+            ty: ArgumentType::OptionalSingle,
+            env: None,
+            env_separator: None,
+            default: Some(vec!["fallback".to_string()]),
+        };
+
+        Raw::fill_from_default(&mut raw, &arg);
+
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw.value_source(0), RawSource::CommandLine);
+    }
+
+    #[test]
+    fn fill_from_env_splits_on_whitespace_by_default() {
+        std::env::set_var("COMMANDER_TEST_ENV_WHITESPACE", "a b  c");
+        let mut raw = Raw::new(vec![]);
+        let arg = Argument {
+            ty: ArgumentType::OptionalMultiple,
+            env: Some("COMMANDER_TEST_ENV_WHITESPACE".to_string()),
+            env_separator: None,
+            default: None,
+        };
+
+        Raw::fill_from_env(&mut raw, &arg);
+        std::env::remove_var("COMMANDER_TEST_ENV_WHITESPACE");
+
+        assert_eq!(&**raw, &["a".to_string(), "b".to_string(), "c".to_string()]);
+        assert_eq!(raw.value_source(0), RawSource::Environment);
+    }
+
+    #[test]
+    fn fill_from_env_splits_on_a_custom_separator() {
+        std::env::set_var("COMMANDER_TEST_ENV_SEPARATOR", "a:b:c");
+        let mut raw = Raw::new(vec![]);
+        let arg = Argument {
+            ty: ArgumentType::OptionalMultiple,
+            env: Some("COMMANDER_TEST_ENV_SEPARATOR".to_string()),
+            env_separator: Some(":".to_string()),
+            default: None,
+        };
+
+        Raw::fill_from_env(&mut raw, &arg);
+        std::env::remove_var("COMMANDER_TEST_ENV_SEPARATOR");
+
+        assert_eq!(&**raw, &["a".to_string(), "b".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn fill_from_env_is_skipped_when_the_user_already_supplied_a_value() {
+        std::env::set_var("COMMANDER_TEST_ENV_SKIPPED", "ignored");
+        let mut raw = Raw::new(vec!["typed".to_string()]);
+        let arg = Argument {
+            ty: ArgumentType::OptionalSingle,
+            env: Some("COMMANDER_TEST_ENV_SKIPPED".to_string()),
+            env_separator: None,
+            default: None,
+        };
+
+        Raw::fill_from_env(&mut raw, &arg);
+        std::env::remove_var("COMMANDER_TEST_ENV_SKIPPED");
+
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw.value_source(0), RawSource::CommandLine);
+    }
+
+    #[test]
+    fn range_parser_accepts_an_in_range_value() {
+        let raw = Raw::new(vec!["5".to_string()]);
+        assert_eq!(raw.parse_one_with(RangeParser::new(1, 10)), Ok(5));
+    }
+
+    #[test]
+    fn range_parser_rejects_an_out_of_range_value() {
+        let raw = Raw::new(vec!["50".to_string()]);
+        let err = raw.parse_one_with(RangeParser::new(1, 10)).unwrap_err();
+        assert_eq!(err.value(), "50");
+        assert_eq!(err.target(), "integer in range 1..=10");
+    }
+
+    #[test]
+    fn possible_values_parser_accepts_an_allowed_value() {
+        let raw = Raw::new(vec!["red".to_string(), "blue".to_string()]);
+        let parser = PossibleValuesParser::new(vec!["red", "green", "blue"]);
+        assert_eq!(raw.parse_with(parser), Ok(vec!["red".to_string(), "blue".to_string()]));
+    }
+
+    #[test]
+    fn possible_values_parser_rejects_an_unknown_value() {
+        let raw = Raw::new(vec!["purple".to_string()]);
+        let parser = PossibleValuesParser::new(vec!["red", "green", "blue"]);
+        let err = raw.parse_with(parser).unwrap_err();
+        assert_eq!(err.value(), "purple");
+    }
+
+    #[test]
+    fn parse_with_accepts_a_closure_as_a_raw_parser() {
+        let raw = Raw::new(vec!["7".to_string()]);
+        let doubled = raw.parse_with(|s: &str| {
+            s.parse::<i32>()
+                .map(|n| n * 2)
+                .map_err(|_| RawConvertError::new(s, "i32", 0))
+        });
+
+        assert_eq!(doubled, Ok(vec![14]));
+    }
+}